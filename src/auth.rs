@@ -0,0 +1,104 @@
+use anyhow::Result;
+use spin_sdk::http::{Request, Response};
+use spin_sdk::variables;
+
+use crate::error::ApiError;
+
+/// The Spin variable holding the expected API token. Unset or empty means
+/// auth is disabled.
+const TOKEN_VARIABLE: &str = "api_token";
+
+/// Checks `req` against the configured API token, mirroring the
+/// `API-Token`/`Authorization: Bearer` convention used by other services.
+/// Returns `Ok(Some(response))` with a `401` when the token is missing or
+/// wrong, `Ok(None)` when the request may proceed (including when no token
+/// is configured, which disables auth entirely). Fails closed (returns
+/// `Err`) if the variable provider itself errors, rather than silently
+/// disabling auth.
+pub fn authorize(req: &Request) -> Result<Option<Response>> {
+    let expected = match variables::get(TOKEN_VARIABLE) {
+        Ok(token) if !token.is_empty() => token,
+        Ok(_) => return Ok(None),
+        // The variable simply isn't configured: auth stays disabled. Any
+        // other provider error is unexpected and we fail closed rather than
+        // silently letting unauthenticated requests through.
+        Err(variables::Error::Undefined(_)) => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+
+    let provided = extract_token(
+        req.header("api-token").and_then(|h| h.as_str()),
+        req.header("authorization").and_then(|h| h.as_str()),
+    );
+
+    match provided {
+        Some(token) if constant_time_eq(&token, &expected) => Ok(None),
+        _ => Ok(Some(
+            ApiError::Unauthorized("missing or invalid API token".to_string()).into_response(),
+        )),
+    }
+}
+
+/// Pulls the bearer token out of either the `API-Token` header or an
+/// `Authorization: Bearer <token>` header, preferring `API-Token`.
+fn extract_token(
+    api_token_header: Option<&str>,
+    authorization_header: Option<&str>,
+) -> Option<String> {
+    api_token_header.map(str::to_string).or_else(|| {
+        authorization_header
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .map(str::to_string)
+    })
+}
+
+/// Compares two strings for equality in constant time with respect to their
+/// contents, so a timing side channel can't be used to guess the configured
+/// token one byte at a time.
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b.iter()).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extract_token_prefers_api_token_header() {
+        assert_eq!(
+            extract_token(Some("secret"), Some("Bearer other")),
+            Some("secret".to_string())
+        );
+    }
+
+    #[test]
+    fn extract_token_falls_back_to_bearer_auth() {
+        assert_eq!(
+            extract_token(None, Some("Bearer secret")),
+            Some("secret".to_string())
+        );
+    }
+
+    #[test]
+    fn extract_token_ignores_non_bearer_authorization() {
+        assert_eq!(extract_token(None, Some("Basic dXNlcjpwYXNz")), None);
+    }
+
+    #[test]
+    fn extract_token_is_none_when_absent() {
+        assert_eq!(extract_token(None, None), None);
+    }
+
+    #[test]
+    fn constant_time_eq_matches_ordinary_equality() {
+        assert!(constant_time_eq("secret", "secret"));
+        assert!(!constant_time_eq("secret", "wrong"));
+        assert!(!constant_time_eq("secret", "secrets"));
+        assert!(!constant_time_eq("", "secret"));
+        assert!(constant_time_eq("", ""));
+    }
+}
@@ -0,0 +1,193 @@
+use anyhow::{anyhow, Result};
+use spin_sdk::http::Request;
+use std::io::{Read, Write};
+
+/// The content-coding negotiated for a request or response body.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    Identity,
+    Gzip,
+    Zlib,
+    Brotli,
+    Zstd,
+}
+
+impl Encoding {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Identity => "identity",
+            Self::Gzip => "gzip",
+            Self::Zlib => "zlib",
+            Self::Brotli => "br",
+            Self::Zstd => "zstd",
+        }
+    }
+
+    fn from_token(token: &str) -> Option<Self> {
+        match token.trim().to_ascii_lowercase().as_str() {
+            "gzip" | "x-gzip" => Some(Self::Gzip),
+            "zlib" | "deflate" => Some(Self::Zlib),
+            "br" => Some(Self::Brotli),
+            "zstd" => Some(Self::Zstd),
+            "identity" => Some(Self::Identity),
+            _ => None,
+        }
+    }
+}
+
+/// Decompresses `req`'s body according to its `Content-Encoding` header,
+/// leaving it untouched when the header is absent or `identity`.
+pub fn decompress_request_body(req: &Request) -> Result<Vec<u8>> {
+    let body = req.body().as_ref();
+    let encoding = req
+        .header("content-encoding")
+        .and_then(|h| h.as_str())
+        .and_then(Encoding::from_token)
+        .unwrap_or(Encoding::Identity);
+
+    decompress(encoding, body)
+}
+
+/// Caps how large a decompressed body may grow, so a small compressed
+/// payload can't be used as a decompression bomb against the batch
+/// endpoint. 10 MiB comfortably covers any realistic batch of sentences.
+const MAX_DECOMPRESSED_BYTES: u64 = 10 * 1024 * 1024;
+
+fn decompress(encoding: Encoding, body: &[u8]) -> Result<Vec<u8>> {
+    match encoding {
+        Encoding::Identity => {
+            if body.len() as u64 > MAX_DECOMPRESSED_BYTES {
+                return Err(anyhow!(
+                    "body exceeds {MAX_DECOMPRESSED_BYTES} byte limit"
+                ));
+            }
+            Ok(body.to_vec())
+        }
+        Encoding::Gzip => {
+            read_bounded(flate2::read::GzDecoder::new(body), MAX_DECOMPRESSED_BYTES)
+        }
+        Encoding::Zlib => read_bounded(
+            flate2::read::ZlibDecoder::new(body),
+            MAX_DECOMPRESSED_BYTES,
+        ),
+        Encoding::Brotli => read_bounded(
+            brotli::Decompressor::new(body, 4096),
+            MAX_DECOMPRESSED_BYTES,
+        ),
+        Encoding::Zstd => read_bounded(
+            zstd::stream::read::Decoder::new(body)?,
+            MAX_DECOMPRESSED_BYTES,
+        ),
+    }
+}
+
+/// Reads at most `limit` bytes from `reader`, erroring out instead of
+/// reading further if the stream has more than that to give.
+fn read_bounded(mut reader: impl Read, limit: u64) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    (&mut reader).take(limit).read_to_end(&mut out)?;
+
+    if out.len() as u64 == limit {
+        let mut probe = [0u8; 1];
+        if reader.read(&mut probe)? > 0 {
+            return Err(anyhow!("decompressed payload exceeds {limit} byte limit"));
+        }
+    }
+
+    Ok(out)
+}
+
+/// Picks the best encoding `req` advertises support for via `Accept-Encoding`,
+/// preferring (in order) zstd, brotli, gzip, zlib, then falling back to
+/// identity when the client sends nothing we support.
+pub fn negotiate_response_encoding(req: &Request) -> Encoding {
+    let Some(header) = req.header("accept-encoding").and_then(|h| h.as_str()) else {
+        return Encoding::Identity;
+    };
+
+    let accepted: Vec<Encoding> = header
+        .split(',')
+        .filter_map(|token| Encoding::from_token(token.split(';').next().unwrap_or(token)))
+        .collect();
+
+    [Encoding::Zstd, Encoding::Brotli, Encoding::Gzip, Encoding::Zlib]
+        .into_iter()
+        .find(|candidate| accepted.contains(candidate))
+        .unwrap_or(Encoding::Identity)
+}
+
+/// Compresses `body` with `encoding`, returning it unchanged for `Identity`.
+pub fn compress(encoding: Encoding, body: &[u8]) -> Result<Vec<u8>> {
+    match encoding {
+        Encoding::Identity => Ok(body.to_vec()),
+        Encoding::Gzip => {
+            let mut encoder =
+                flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(body)?;
+            Ok(encoder.finish()?)
+        }
+        Encoding::Zlib => {
+            let mut encoder =
+                flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(body)?;
+            Ok(encoder.finish()?)
+        }
+        Encoding::Brotli => {
+            let mut out = Vec::new();
+            let params = brotli::enc::BrotliEncoderParams::default();
+            brotli::BrotliCompress(&mut std::io::Cursor::new(body), &mut out, &params)
+                .map_err(|e| anyhow!("brotli compression failed: {e}"))?;
+            Ok(out)
+        }
+        Encoding::Zstd => Ok(zstd::stream::encode_all(body, 0)?),
+    }
+}
+
+/// The `Content-Encoding` value to send for a negotiated encoding, or `None`
+/// for identity (in which case the header should be omitted).
+pub fn content_encoding_header(encoding: Encoding) -> Option<&'static str> {
+    (encoding != Encoding::Identity).then(|| encoding.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_token_is_case_insensitive() {
+        assert_eq!(Encoding::from_token("GZIP"), Some(Encoding::Gzip));
+        assert_eq!(Encoding::from_token("Br"), Some(Encoding::Brotli));
+        assert_eq!(Encoding::from_token("ZSTD"), Some(Encoding::Zstd));
+        assert_eq!(Encoding::from_token("Deflate"), Some(Encoding::Zlib));
+        assert_eq!(Encoding::from_token("bogus"), None);
+    }
+
+    #[test]
+    fn compress_decompress_round_trips_for_every_encoding() {
+        let body = b"I am so happy today".repeat(8);
+        for encoding in [
+            Encoding::Identity,
+            Encoding::Gzip,
+            Encoding::Zlib,
+            Encoding::Brotli,
+            Encoding::Zstd,
+        ] {
+            let compressed = compress(encoding, &body).unwrap();
+            let decompressed = decompress(encoding, &compressed).unwrap();
+            assert_eq!(decompressed, body, "round trip failed for {encoding:?}");
+        }
+    }
+
+    #[test]
+    fn read_bounded_errors_past_the_limit() {
+        let data = vec![0u8; 16];
+        assert_eq!(read_bounded(data.as_slice(), 16).unwrap().len(), 16);
+        assert!(read_bounded(data.as_slice(), 15).is_err());
+    }
+
+    #[test]
+    fn content_encoding_header_omits_identity() {
+        assert_eq!(content_encoding_header(Encoding::Identity), None);
+        assert_eq!(content_encoding_header(Encoding::Gzip), Some("gzip"));
+    }
+}
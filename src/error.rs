@@ -0,0 +1,127 @@
+use serde::Serialize;
+use spin_sdk::http::Response;
+
+/// A structured API error, each variant mapping to an HTTP status code and
+/// serialized as a consistent `{"error": {...}}` JSON body.
+#[derive(Debug)]
+pub enum ApiError {
+    /// 400: the request itself is malformed (e.g. can't be decompressed).
+    BadRequest(String),
+    /// 401: missing or invalid API token.
+    Unauthorized(String),
+    /// 422: well-formed body that doesn't deserialize into the expected shape.
+    InvalidJson(String),
+    /// 500: sentiment analysis errored (the inference call or a cache
+    /// lookup failed). A model response that doesn't parse into a sentiment
+    /// is not reported here; it yields an empty sentiment instead.
+    InferenceFailed(String),
+    /// 500: an otherwise-uncategorized internal failure.
+    Internal(String),
+    /// 503: the cache backend is unavailable.
+    StorageError(String),
+}
+
+impl ApiError {
+    fn status(&self) -> u16 {
+        match self {
+            Self::BadRequest(_) => 400,
+            Self::Unauthorized(_) => 401,
+            Self::InvalidJson(_) => 422,
+            Self::InferenceFailed(_) | Self::Internal(_) => 500,
+            Self::StorageError(_) => 503,
+        }
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::BadRequest(_) => "bad_request",
+            Self::Unauthorized(_) => "unauthorized",
+            Self::InvalidJson(_) => "invalid_json",
+            Self::InferenceFailed(_) => "inference_failed",
+            Self::Internal(_) => "internal",
+            Self::StorageError(_) => "storage_error",
+        }
+    }
+
+    fn message(&self) -> &str {
+        match self {
+            Self::BadRequest(msg)
+            | Self::Unauthorized(msg)
+            | Self::InvalidJson(msg)
+            | Self::InferenceFailed(msg)
+            | Self::Internal(msg)
+            | Self::StorageError(msg) => msg,
+        }
+    }
+
+    /// Renders this error as the JSON response that should be sent to the
+    /// client, with the matching HTTP status code.
+    pub fn into_response(self) -> Response {
+        #[derive(Serialize)]
+        struct Body<'a> {
+            error: ErrorBody<'a>,
+        }
+
+        #[derive(Serialize)]
+        struct ErrorBody<'a> {
+            kind: &'a str,
+            message: &'a str,
+        }
+
+        let status = self.status();
+        let body = Body {
+            error: ErrorBody {
+                kind: self.kind(),
+                message: self.message(),
+            },
+        };
+        // Serializing a fixed, known-good shape cannot fail.
+        let body = serde_json::to_string(&body).expect("ApiError body always serializes");
+
+        let mut builder = Response::builder();
+        builder.status(status);
+        builder.header("content-type", "application/json");
+        builder.body(body);
+        builder.build()
+    }
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.kind(), self.message())
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_and_kind_match_each_variant() {
+        let cases: Vec<(ApiError, u16, &str)> = vec![
+            (ApiError::BadRequest("x".into()), 400, "bad_request"),
+            (ApiError::Unauthorized("x".into()), 401, "unauthorized"),
+            (ApiError::InvalidJson("x".into()), 422, "invalid_json"),
+            (
+                ApiError::InferenceFailed("x".into()),
+                500,
+                "inference_failed",
+            ),
+            (ApiError::Internal("x".into()), 500, "internal"),
+            (ApiError::StorageError("x".into()), 503, "storage_error"),
+        ];
+
+        for (err, status, kind) in cases {
+            assert_eq!(err.status(), status, "status for {kind}");
+            assert_eq!(err.kind(), kind);
+        }
+    }
+
+    #[test]
+    fn display_includes_kind_and_message() {
+        let err = ApiError::BadRequest("bad body".to_string());
+        assert_eq!(err.to_string(), "bad_request: bad body");
+    }
+}
@@ -4,12 +4,21 @@ use anyhow::Result;
 use spin_sdk::{
     http::{IntoResponse, Params, Request, Response, Router},
     http_component,
-    key_value::Store,
     llm::{infer_with_options, InferencingModel::Llama2Chat},
 };
 
 use serde::{Deserialize, Serialize};
 
+mod auth;
+mod compression;
+mod error;
+mod metrics;
+mod store;
+
+use error::ApiError;
+use metrics::METRICS;
+use store::{open_store, SentimentStore};
+
 #[derive(Deserialize)]
 pub struct SentimentAnalysisRequest {
     pub sentence: String,
@@ -20,6 +29,32 @@ pub struct SentimentAnalysisResponse {
     pub sentiment: String,
 }
 
+/// Accepts either a bare JSON array of sentences or `{"sentences": [...]}`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum BatchSentimentAnalysisRequest {
+    Sentences(Vec<String>),
+    Wrapped { sentences: Vec<String> },
+}
+
+impl BatchSentimentAnalysisRequest {
+    fn into_sentences(self) -> Vec<String> {
+        match self {
+            Self::Sentences(sentences) => sentences,
+            Self::Wrapped { sentences } => sentences,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct BatchSentimentAnalysisEntry {
+    pub sentence: String,
+    pub sentiment: String,
+    pub status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
 const PROMPT: &str = r#"\
 <<SYS>>
 You are a bot that generates sentiment analysis responses. Respond with a single positive, negative, or neutral.
@@ -43,9 +78,22 @@ User: {SENTENCE}
 /// A Spin HTTP component that internally routes requests.
 #[http_component]
 fn handle_route(req: Request) -> Response {
+    if req.path().starts_with("/api/") {
+        match auth::authorize(&req) {
+            Ok(Some(unauthorized)) => return unauthorized,
+            Ok(None) => {}
+            Err(err) => return ApiError::Internal(err.to_string()).into_response(),
+        }
+    }
+
     let mut router = Router::new();
     router.any("/api/*", not_found);
     router.post("/api/sentiment-analysis", perform_sentiment_analysis);
+    router.post(
+        "/api/sentiment-analysis/batch",
+        perform_batch_sentiment_analysis,
+    );
+    router.get("/metrics", report_metrics);
     router.handle(req)
 }
 
@@ -53,28 +101,85 @@ fn not_found(_: Request, _: Params) -> Result<impl IntoResponse> {
     Ok(Response::new(404, "Not found"))
 }
 
+/// Reports cache hit/miss and inference counters in Prometheus text
+/// exposition format.
+fn report_metrics(_req: Request, _params: Params) -> Result<impl IntoResponse> {
+    Ok(Response::new(200, METRICS.render()))
+}
+
 fn perform_sentiment_analysis(req: Request, _params: Params) -> Result<impl IntoResponse> {
-    let request = body_json_to_map(&req)?;
+    Ok(perform_sentiment_analysis_inner(&req).unwrap_or_else(ApiError::into_response))
+}
+
+fn perform_sentiment_analysis_inner(req: &Request) -> Result<Response, ApiError> {
+    let request = body_json_to_map(req)?;
     // Do some basic cleanup on the input
     let sentence = request.sentence.trim();
-    println!("Performing sentiment analysis on: {}", sentence);
 
-    // Prepare the KV store
-    let kv = Store::open_default()?;
+    let store = open_store().map_err(|e| ApiError::StorageError(e.to_string()))?;
+
+    let sentiment = analyze_sentence(store.as_ref(), sentence)
+        .map_err(|e| ApiError::InferenceFailed(e.to_string()))?;
+    let resp = SentimentAnalysisResponse { sentiment };
+
+    encode_ok_response(200, &resp, req)
+}
+
+fn perform_batch_sentiment_analysis(req: Request, _params: Params) -> Result<impl IntoResponse> {
+    Ok(perform_batch_sentiment_analysis_inner(&req).unwrap_or_else(ApiError::into_response))
+}
 
-    // If the sentiment of the sentence is already in the KV store, return it
-    if let Ok(sentiment) = kv.get(sentence) {
-        println!("Found sentence in KV store returning cached sentiment.");
-        let resp = SentimentAnalysisResponse {
-            sentiment: String::from_utf8(sentiment.unwrap())?,
+fn perform_batch_sentiment_analysis_inner(req: &Request) -> Result<Response, ApiError> {
+    let body = compression::decompress_request_body(req)
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    let request: BatchSentimentAnalysisRequest =
+        serde_json::from_slice(&body).map_err(|e| ApiError::InvalidJson(e.to_string()))?;
+    let sentences = request.into_sentences();
+
+    let store = open_store().map_err(|e| ApiError::StorageError(e.to_string()))?;
+
+    let mut results = Vec::with_capacity(sentences.len());
+    for sentence in sentences {
+        let trimmed = sentence.trim();
+        let entry = match analyze_sentence(store.as_ref(), trimmed) {
+            Ok(sentiment) => BatchSentimentAnalysisEntry {
+                sentence: trimmed.to_string(),
+                sentiment,
+                status: "ok",
+                error: None,
+            },
+            Err(err) => BatchSentimentAnalysisEntry {
+                sentence: trimmed.to_string(),
+                sentiment: String::new(),
+                status: "error",
+                error: Some(err.to_string()),
+            },
         };
+        results.push(entry);
+    }
+
+    encode_ok_response(200, &results, req)
+}
+
+/// Looks up `sentence` in the cache, falling back to inference on a cache
+/// miss and caching the result. Returns an empty string if the model's
+/// response doesn't parse into a [`Sentiment`].
+fn analyze_sentence(store: &dyn SentimentStore, sentence: &str) -> Result<String> {
+    METRICS.record_request();
+    println!("Performing sentiment analysis on: {}", sentence);
 
-        return send_ok_response(200, resp);
+    // If the sentiment of the sentence is already cached, return it
+    if let Some(sentiment) = store.get(sentence)? {
+        METRICS.record_cache_hit();
+        println!("Found sentence in cache, returning cached sentiment.");
+        return Ok(sentiment.to_string());
     }
-    println!("Sentence not found in KV store.");
+    METRICS.record_cache_miss();
+    println!("Sentence not found in cache.");
 
     // Perform sentiment analysis
     println!("Running inference...");
+    let inference_started = std::time::Instant::now();
     let inferencing_result = infer_with_options(
         Llama2Chat,
         &PROMPT.replace("{SENTENCE}", sentence),
@@ -95,43 +200,58 @@ fn perform_sentiment_analysis(req: Request, _params: Params) -> Result<impl Into
         .unwrap_or_default()
         .parse::<Sentiment>();
     println!("Got sentiment: {sentiment:?}");
+    METRICS.record_inference(inference_started.elapsed(), sentiment.is_err());
 
     if let Ok(sentiment) = sentiment {
-        println!("Caching sentiment in KV store.");
-        let _ = kv.set(sentence, sentiment.as_str().as_bytes());
+        println!("Caching sentiment.");
+        let _ = store.set(sentence, sentiment);
     }
 
-    // Cache result in KV store
-    let resp = SentimentAnalysisResponse {
-        sentiment: sentiment
-            .as_ref()
-            .map(ToString::to_string)
-            .unwrap_or_default(),
-    };
-
-    send_ok_response(200, resp)
+    Ok(sentiment
+        .as_ref()
+        .map(ToString::to_string)
+        .unwrap_or_default())
 }
 
-fn send_ok_response(code: u16, resp: SentimentAnalysisResponse) -> Result<Response> {
-    let resp_str = serde_json::to_string(&resp)?;
-    Ok(Response::new(code, resp_str))
+/// Serializes `resp` to JSON and compresses it according to the request's
+/// `Accept-Encoding` header, setting `Content-Encoding` to match.
+fn encode_ok_response<T: Serialize>(
+    code: u16,
+    resp: &T,
+    req: &Request,
+) -> Result<Response, ApiError> {
+    let resp_bytes = serde_json::to_vec(resp).map_err(|e| ApiError::Internal(e.to_string()))?;
+
+    let encoding = compression::negotiate_response_encoding(req);
+    let body = compression::compress(encoding, &resp_bytes)
+        .map_err(|e| ApiError::Internal(e.to_string()))?;
+
+    let mut builder = Response::builder();
+    builder.status(code);
+    builder.header("content-type", "application/json");
+    if let Some(content_encoding) = compression::content_encoding_header(encoding) {
+        builder.header("content-encoding", content_encoding);
+    }
+    builder.body(body);
+    Ok(builder.build())
 }
 
-fn body_json_to_map(req: &Request) -> Result<SentimentAnalysisRequest> {
-    let body = String::from_utf8(req.body().as_ref().to_vec())?;
+fn body_json_to_map(req: &Request) -> Result<SentimentAnalysisRequest, ApiError> {
+    let body = compression::decompress_request_body(req)
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
 
-    Ok(SentimentAnalysisRequest { sentence: body })
+    serde_json::from_slice(&body).map_err(|e| ApiError::InvalidJson(e.to_string()))
 }
 
-#[derive(Copy, Clone, Debug)]
-enum Sentiment {
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Sentiment {
     Positive,
     Negative,
     Neutral,
 }
 
 impl Sentiment {
-    fn as_str(&self) -> &str {
+    pub(crate) fn as_str(&self) -> &str {
         match self {
             Self::Positive => "positive",
             Self::Negative => "negative",
@@ -177,6 +297,23 @@ mod tests {
         assert_eq!(request.sentence, "I am so happy today");
     }
 
+    #[test]
+    fn deserialize_batch_sentiment_analysis_request_bare_array() {
+        let json = r#"["I am so happy today", "I am so sad today"]"#;
+        let request: BatchSentimentAnalysisRequest = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            request.into_sentences(),
+            vec!["I am so happy today", "I am so sad today"]
+        );
+    }
+
+    #[test]
+    fn deserialize_batch_sentiment_analysis_request_wrapped() {
+        let json = r#"{"sentences": ["I am so happy today"]}"#;
+        let request: BatchSentimentAnalysisRequest = serde_json::from_str(json).unwrap();
+        assert_eq!(request.into_sentences(), vec!["I am so happy today"]);
+    }
+
     #[test]
     fn serialize_sentiment_analysis_response() {
         let response = SentimentAnalysisResponse {
@@ -185,4 +322,18 @@ mod tests {
         let json = serde_json::to_string(&response).unwrap();
         assert_eq!(json, r#"{"sentiment":"positive"}"#);
     }
+
+    #[test]
+    fn in_memory_store_round_trips_sentiment() {
+        let store = store::InMemoryStore::default();
+        assert_eq!(store.get("I am so happy today").unwrap(), None);
+
+        store
+            .set("I am so happy today", Sentiment::Positive)
+            .unwrap();
+        assert_eq!(
+            store.get("I am so happy today").unwrap(),
+            Some(Sentiment::Positive)
+        );
+    }
 }
@@ -0,0 +1,133 @@
+use spin_sdk::key_value::Store;
+
+/// Keys under which counters are persisted in the default KV store. Spin
+/// instantiates a fresh guest per request, so these can't live in a
+/// process-global: they're stored the same way the sentiment cache is, in
+/// the external KV store, so they actually accumulate across requests.
+const REQUESTS_KEY: &str = "metrics:requests_total";
+const CACHE_HITS_KEY: &str = "metrics:cache_hits_total";
+const CACHE_MISSES_KEY: &str = "metrics:cache_misses_total";
+const INFERENCE_INVOCATIONS_KEY: &str = "metrics:inference_invocations_total";
+const INFERENCE_FAILURES_KEY: &str = "metrics:inference_failures_total";
+// Stored as whole microseconds so it fits the same u64 counter encoding.
+const INFERENCE_DURATION_MICROS_KEY: &str = "metrics:inference_duration_micros_sum";
+
+/// Counters tracking cache hits, misses, and inference latency, reported
+/// via `GET /metrics` in Prometheus text exposition format.
+///
+/// These are approximate, not authoritative, under concurrent traffic:
+/// Spin's key-value store has no atomic increment or compare-and-swap, so
+/// `increment` below is a plain read-modify-write and two requests racing
+/// on the same counter can lose an update. Good enough for the hit-rate
+/// and latency trends operators actually watch; not a source of truth for
+/// exact counts.
+pub struct Metrics;
+
+pub static METRICS: Metrics = Metrics;
+
+impl Metrics {
+    pub fn record_request(&self) {
+        let _ = increment(REQUESTS_KEY, 1);
+    }
+
+    pub fn record_cache_hit(&self) {
+        let _ = increment(CACHE_HITS_KEY, 1);
+    }
+
+    pub fn record_cache_miss(&self) {
+        let _ = increment(CACHE_MISSES_KEY, 1);
+    }
+
+    pub fn record_inference(&self, duration: std::time::Duration, failed: bool) {
+        let _ = increment(INFERENCE_INVOCATIONS_KEY, 1);
+        let _ = increment(INFERENCE_DURATION_MICROS_KEY, duration.as_micros() as u64);
+        if failed {
+            let _ = increment(INFERENCE_FAILURES_KEY, 1);
+        }
+    }
+
+    /// Renders the current counters in Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let kv = Store::open_default().ok();
+        let read = |key: &str| kv.as_ref().map(|kv| read_counter(kv, key)).unwrap_or(0);
+
+        format_metrics(
+            read(REQUESTS_KEY),
+            read(CACHE_HITS_KEY),
+            read(CACHE_MISSES_KEY),
+            read(INFERENCE_INVOCATIONS_KEY),
+            read(INFERENCE_FAILURES_KEY),
+            read(INFERENCE_DURATION_MICROS_KEY) as f64 / 1_000_000.0,
+        )
+    }
+}
+
+/// Non-atomic read-modify-write: Spin's KV store has no increment or CAS
+/// primitive, so concurrent requests racing on the same key can clobber
+/// each other's update. See the caveat on [`Metrics`].
+fn increment(key: &str, delta: u64) -> anyhow::Result<()> {
+    let kv = Store::open_default()?;
+    let current = read_counter(&kv, key);
+    kv.set(key, (current + delta).to_string().as_bytes())?;
+    Ok(())
+}
+
+fn read_counter(kv: &Store, key: &str) -> u64 {
+    kv.get(key)
+        .ok()
+        .flatten()
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(0)
+}
+
+/// Pure formatting logic, kept separate from `Metrics::render` so it can be
+/// unit-tested without a live Spin KV store.
+fn format_metrics(
+    requests: u64,
+    hits: u64,
+    misses: u64,
+    invocations: u64,
+    failures: u64,
+    duration_sum_seconds: f64,
+) -> String {
+    format!(
+        "# HELP sentiment_analysis_requests_total Total sentiment analysis requests.\n\
+         # TYPE sentiment_analysis_requests_total counter\n\
+         sentiment_analysis_requests_total {requests}\n\
+         # HELP sentiment_analysis_cache_hits_total Cache hits serving a cached sentiment.\n\
+         # TYPE sentiment_analysis_cache_hits_total counter\n\
+         sentiment_analysis_cache_hits_total {hits}\n\
+         # HELP sentiment_analysis_cache_misses_total Cache misses requiring inference.\n\
+         # TYPE sentiment_analysis_cache_misses_total counter\n\
+         sentiment_analysis_cache_misses_total {misses}\n\
+         # HELP sentiment_analysis_inference_invocations_total Inference calls made.\n\
+         # TYPE sentiment_analysis_inference_invocations_total counter\n\
+         sentiment_analysis_inference_invocations_total {invocations}\n\
+         # HELP sentiment_analysis_inference_failures_total Inference results that failed to parse into a sentiment.\n\
+         # TYPE sentiment_analysis_inference_failures_total counter\n\
+         sentiment_analysis_inference_failures_total {failures}\n\
+         # HELP sentiment_analysis_inference_duration_seconds Time spent in inference calls.\n\
+         # TYPE sentiment_analysis_inference_duration_seconds summary\n\
+         sentiment_analysis_inference_duration_seconds_sum {duration_sum_seconds}\n\
+         sentiment_analysis_inference_duration_seconds_count {invocations}\n"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_metrics_renders_prometheus_text_exposition_format() {
+        let rendered = format_metrics(10, 6, 4, 4, 1, 2.5);
+
+        assert!(rendered.contains("sentiment_analysis_requests_total 10\n"));
+        assert!(rendered.contains("sentiment_analysis_cache_hits_total 6\n"));
+        assert!(rendered.contains("sentiment_analysis_cache_misses_total 4\n"));
+        assert!(rendered.contains("sentiment_analysis_inference_invocations_total 4\n"));
+        assert!(rendered.contains("sentiment_analysis_inference_failures_total 1\n"));
+        assert!(rendered.contains("sentiment_analysis_inference_duration_seconds_sum 2.5\n"));
+        assert!(rendered.contains("sentiment_analysis_inference_duration_seconds_count 4\n"));
+    }
+}